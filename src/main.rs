@@ -1,13 +1,26 @@
+mod mandelbrot;
+
 use std::time::Instant;
 use clap::Parser;
-use num::Complex;
 use image::{Rgb, RgbImage};
 use indicatif::{ProgressBar, ProgressStyle};
 use show_image::{create_window, event};
-use rayon::prelude::*;
+use mandelbrot::Mode;
+use mandelbrot::buddhabrot::render_buddhabrot;
+use mandelbrot::color::{self, Palette};
+use mandelbrot::sample::{check_precision, progressive_work, sample_grid, sample_grid_progressive, SampleResult};
+use mandelbrot::simd::sample_grid_simd;
+
+/// Amount, in screen pixels at the current zoom, that an arrow-key press pans the view.
+const PAN_STEP_PIXELS: f64 = 40.0;
 
+/// Factor the zoom is multiplied (or divided) by for a single scroll-wheel notch.
+const ZOOM_STEP_FACTOR: f64 = 1.1;
 
-#[derive(Parser, Debug)]
+/// Number of iterations added or removed by a single `+`/`-` key press.
+const ITERATION_STEP: u32 = 50;
+
+#[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
 struct Args {
     /// Output file path to use instead of the Image preview window.
@@ -45,71 +58,272 @@ struct Args {
     /// Number of worker threads to run the calculation on.
     #[arg(short, long, default_value_t = 1)]
     workers: usize,
+
+    /// Number of samples to average per pixel (supersampling). Rounded down to the nearest perfect square.
+    #[arg(short, long, default_value_t = 1)]
+    samples: u32,
+
+    /// Use smooth (fractional) escape time instead of a raw iteration count.
+    #[arg(long, default_value_t = false)]
+    smooth: bool,
+
+    /// Rendering mode.
+    #[arg(long, value_enum, default_value_t = Mode::Mandelbrot)]
+    mode: Mode,
+
+    /// Render the Nebulabrot variant of the Buddhabrot (tri-pass RGB orbit density).
+    #[arg(long, default_value_t = false)]
+    nebula: bool,
+
+    /// Number of random orbits to sample when rendering a Buddhabrot.
+    #[arg(long, default_value_t = 1_000_000)]
+    buddhabrot_samples: u64,
+
+    /// Color by estimated distance to the set boundary instead of escape time, for crisp filaments.
+    #[arg(long, default_value_t = false)]
+    distance_estimate: bool,
+
+    /// Color palette to map the normalized escape value through.
+    #[arg(long, value_enum, default_value_t = Palette::Grayscale)]
+    palette: Palette,
+
+    /// Path to a custom gradient file (one `#RRGGBB` color per line), overriding `--palette`.
+    #[arg(long)]
+    gradient_file: Option<String>,
+
+    /// Real component of the fixed Julia set constant, used when `--mode julia`.
+    #[arg(long, default_value_t = -0.7)]
+    julia_real: f64,
+
+    /// Imaginary component of the fixed Julia set constant, used when `--mode julia`.
+    #[arg(long, default_value_t = 0.27015)]
+    julia_imag: f64,
+
+    /// Use the SIMD-vectorized escape-time sampler. Falls back to the scalar sampler for
+    /// `--mode julia` and whenever `--smooth` or `--distance-estimate` need per-iteration state.
+    #[arg(long, default_value_t = false)]
+    simd: bool,
 }
 
-#[derive(Copy, Clone)]
-struct PixelLocation {
-    x: u32,
-    y: u32,
+/// Whether the current `args` can use the vectorized sampler, or must fall back to the scalar one.
+///
+/// The SIMD sampler only evaluates the exact pixel center, so it can't honor `--samples`
+/// supersampling (the scalar path always jitters, even at `--samples 1`); fall back to scalar
+/// rather than silently aliasing whenever supersampling is requested.
+fn use_simd(args: &Args) -> bool {
+    args.simd && args.mode == Mode::Mandelbrot && !args.smooth && !args.distance_estimate && args.samples <= 1
 }
 
 #[show_image::main]
 fn main() {
     let now = Instant::now();
     let args = Args::parse();
-    let offset = Complex::new(args.real_offset, args.complex_offset);
-    let center = Complex::new(args.x_res as f64, args.y_res as f64) / args.zoom / 2f64;
 
-    let progress_bar = build_progress_bar((args.x_res * args.y_res) as u64);
-    progress_bar.set_message("Sampling Mandelbrot");
-
-    let mut image = RgbImage::new(args.x_res, args.y_res);
-    let thread_pool = rayon::ThreadPoolBuilder::new().num_threads(args.workers).build().unwrap();
-    thread_pool.install(|| {
-        image.enumerate_pixels_mut().par_bridge().for_each(|(x, y, pixel)| {
-            let complex_location: Complex<f64> = pixel_to_complex(PixelLocation { x, y }, center, offset, args.zoom);
-            let color = match sample_mandelbrot(complex_location, args.threshold, args.max_iterations) {
-                Some(iterations) => iterations_to_color(iterations, args.max_iterations),
-                None => Rgb([0, 0, 0])
-            };
-            *pixel = color;
-            progress_bar.inc(1);
-        });
-    });
-
-    match args.output {
+    match &args.output {
         Some(output) => {
+            let progress_bar = build_progress_bar(progress_bar_len(&args));
+            progress_bar.set_message("Sampling Mandelbrot");
+            let image = render(&args, &progress_bar);
             progress_bar.set_message("Saving image");
             image.save(output).unwrap();
+            progress_bar.finish();
         },
-        None => {
-            progress_bar.set_message("Displaying image");
-            show_image(image).unwrap()
-        }
+        None => show_image(args).unwrap(),
     };
 
-    progress_bar.set_message("Saving image");
-    progress_bar.finish();
-
     let elapsed = now.elapsed().as_millis();
     println!("Finished in {elapsed}ms")
 }
 
-/// Display the image in a window and wait for the user to press escape.
-fn show_image(image: RgbImage)-> Result<(), Box<dyn std::error::Error>> {
+/// Sample the view described by `args` under the selected rendering mode and color the result.
+fn render(args: &Args, progress_bar: &ProgressBar) -> RgbImage {
+    let thread_pool = rayon::ThreadPoolBuilder::new().num_threads(args.workers).build().unwrap();
+    thread_pool.install(|| match args.mode {
+        Mode::Buddhabrot => render_buddhabrot(args, progress_bar),
+        Mode::Mandelbrot | Mode::Julia => {
+            let result = sample_view(args, progress_bar);
+            grid_to_image(&result, args)
+        },
+    })
+}
+
+/// Sample the escape-time grid for `Mode::Mandelbrot`/`Mode::Julia`, using the SIMD sampler when
+/// [`use_simd`] allows it and the scalar sampler otherwise.
+fn sample_view(args: &Args, progress_bar: &ProgressBar) -> SampleResult {
+    if use_simd(args) {
+        let grid = sample_grid_simd(args, progress_bar);
+        let distance = vec![vec![None; args.y_res as usize]; args.x_res as usize];
+        SampleResult { x_res: args.x_res, y_res: args.y_res, grid, distance }
+    } else {
+        sample_grid(args, progress_bar)
+    }
+}
+
+/// Number of units of work the progress bar should expect for the selected rendering mode.
+///
+/// The SIMD sampler visits each pixel exactly once, but the scalar path samples progressively in
+/// coarse-to-fine stages that re-visit pixels covered by earlier, coarser stages — so its total is
+/// larger than `x_res * y_res` and must be sized via [`progressive_work`] to avoid the bar filling
+/// up before the render actually finishes.
+fn progress_bar_len(args: &Args) -> u64 {
+    match args.mode {
+        Mode::Buddhabrot => args.buddhabrot_samples * if args.nebula { 3 } else { 1 },
+        Mode::Mandelbrot | Mode::Julia if use_simd(args) => (args.x_res * args.y_res) as u64,
+        Mode::Mandelbrot | Mode::Julia => progressive_work(args.x_res, args.y_res),
+    }
+}
+
+/// Convert a sampled grid into a displayable image by running each cell through the color pass.
+fn grid_to_image(result: &SampleResult, args: &Args) -> RgbImage {
+    let custom_gradient = args.gradient_file.as_ref()
+        .map(|path| color::load_gradient(path).expect("failed to load gradient file"));
+
+    let mut image = RgbImage::new(result.x_res, result.y_res);
+    for x in 0..result.x_res {
+        for y in 0..result.y_res {
+            let color = if args.distance_estimate {
+                match result.distance[x as usize][y as usize] {
+                    Some(distance) => distance_to_color(distance, args.zoom),
+                    None => Rgb([0, 0, 0]),
+                }
+            } else {
+                match result.grid[x as usize][y as usize] {
+                    Some(iterations) => {
+                        let t = iterations / args.max_iterations as f64;
+                        match &custom_gradient {
+                            Some(stops) => color::gradient(t, stops),
+                            None => args.palette.color(t),
+                        }
+                    },
+                    None => Rgb([0, 0, 0]),
+                }
+            };
+            image.put_pixel(x, y, color);
+        }
+    }
+    image
+}
+
+/// Display the image in an interactive preview window.
+///
+/// Mouse clicks re-center the view, the scroll wheel zooms in and out, the arrow keys pan, `+`/`-`
+/// adjust `max_iterations`, and the set is re-sampled and redrawn after every change. Escape exits.
+fn show_image(mut args: Args) -> Result<(), Box<dyn std::error::Error>> {
     let window = create_window("Mandelbrot", Default::default())?;
-    window.set_image("Mandelbrot", image)?;
+    let thread_pool = rayon::ThreadPoolBuilder::new().num_threads(args.workers).build()?;
+    let mut last_exhaustion_zoom = None;
+    check_precision(&mut args, &mut last_exhaustion_zoom);
+    redraw(&window, &thread_pool, &args)?;
+
+    let mut cursor = (0.0, 0.0);
     for event in window.event_channel()? {
-        if let event::WindowEvent::KeyboardInput(event) = event {
-            if event.input.key_code == Some(event::VirtualKeyCode::Escape)
-                && event.input.state.is_pressed() {
-                break;
-            }
+        let mut dirty = false;
+
+        match event {
+            event::WindowEvent::KeyboardInput(input_event) if input_event.input.state.is_pressed() => {
+                match input_event.input.key_code {
+                    Some(event::VirtualKeyCode::Escape) => break,
+                    Some(event::VirtualKeyCode::Left) => {
+                        args.real_offset -= PAN_STEP_PIXELS / args.zoom;
+                        dirty = true;
+                    },
+                    Some(event::VirtualKeyCode::Right) => {
+                        args.real_offset += PAN_STEP_PIXELS / args.zoom;
+                        dirty = true;
+                    },
+                    Some(event::VirtualKeyCode::Up) => {
+                        args.complex_offset -= PAN_STEP_PIXELS / args.zoom;
+                        dirty = true;
+                    },
+                    Some(event::VirtualKeyCode::Down) => {
+                        args.complex_offset += PAN_STEP_PIXELS / args.zoom;
+                        dirty = true;
+                    },
+                    Some(event::VirtualKeyCode::Equals) | Some(event::VirtualKeyCode::Plus) => {
+                        args.max_iterations += ITERATION_STEP;
+                        dirty = true;
+                    },
+                    Some(event::VirtualKeyCode::Minus) => {
+                        args.max_iterations = args.max_iterations.saturating_sub(ITERATION_STEP).max(1);
+                        dirty = true;
+                    },
+                    _ => {}
+                }
+            },
+            event::WindowEvent::MouseMove(move_event) => {
+                cursor = (move_event.position.x, move_event.position.y);
+            },
+            event::WindowEvent::MouseButton(button_event) => {
+                if button_event.state.is_pressed() && button_event.button == event::MouseButton::Left {
+                    recenter(&mut args, cursor);
+                    dirty = true;
+                }
+            },
+            event::WindowEvent::MouseWheel(wheel_event) => {
+                let notches = match wheel_event.delta {
+                    event::MouseScrollDelta::LineDelta(_, y) => y as f64,
+                    event::MouseScrollDelta::PixelDelta(delta) => delta.y / 100.0,
+                };
+                if notches > 0.0 {
+                    args.zoom *= ZOOM_STEP_FACTOR;
+                    dirty = true;
+                } else if notches < 0.0 {
+                    args.zoom /= ZOOM_STEP_FACTOR;
+                    dirty = true;
+                }
+            },
+            _ => {}
+        }
+
+        if dirty {
+            check_precision(&mut args, &mut last_exhaustion_zoom);
+            redraw(&window, &thread_pool, &args)?;
         }
     }
     Ok(())
 }
 
+/// Recompute `real_offset`/`complex_offset` so that the clicked pixel becomes the new view center.
+fn recenter(args: &mut Args, cursor: (f64, f64)) {
+    let center = (args.x_res as f64 / 2.0, args.y_res as f64 / 2.0);
+    args.real_offset += (cursor.0 - center.0) / args.zoom;
+    args.complex_offset += (cursor.1 - center.1) / args.zoom;
+}
+
+/// Re-sample the view described by `args` and push the result into the preview window, reusing the
+/// caller's `thread_pool` rather than spinning up a new one for every redraw.
+///
+/// In `Mode::Mandelbrot`/`Mode::Julia`, the grid is sampled in coarse-to-fine stages (see
+/// [`sample_grid_progressive`]), redisplaying after each one so the window shows an increasingly
+/// sharp image instead of staying blank for the whole render.
+fn redraw(
+    window: &show_image::WindowProxy,
+    thread_pool: &rayon::ThreadPool,
+    args: &Args,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let progress_bar = build_progress_bar(progress_bar_len(args));
+    progress_bar.set_message("Sampling Mandelbrot");
+
+    thread_pool.install(|| match args.mode {
+        Mode::Buddhabrot => {
+            let image = render_buddhabrot(args, &progress_bar);
+            let _ = window.set_image("Mandelbrot", image);
+        },
+        Mode::Mandelbrot | Mode::Julia if use_simd(args) => {
+            let result = sample_view(args, &progress_bar);
+            let _ = window.set_image("Mandelbrot", grid_to_image(&result, args));
+        },
+        Mode::Mandelbrot | Mode::Julia => {
+            sample_grid_progressive(args, &progress_bar, |stage| {
+                let _ = window.set_image("Mandelbrot", grid_to_image(stage, args));
+            });
+        },
+    });
+
+    progress_bar.finish_and_clear();
+    Ok(())
+}
+
 /// Construct a progress bar with a custom style.
 fn build_progress_bar(len: u64) -> ProgressBar {
     let progress_bar = ProgressBar::new(len);
@@ -121,29 +335,10 @@ fn build_progress_bar(len: u64) -> ProgressBar {
     progress_bar
 }
 
-/// Convert a pixel location to a location on the complex plane.
-fn pixel_to_complex( location: PixelLocation, center: Complex<f64>, offset: Complex<f64>, zoom: f64) -> Complex<f64> {
-    let sample = Complex::new(location.x as f64, location.y as f64) / zoom;
-    sample + offset - center
-}
-
-/// Map the number of iterations to a color.
-fn iterations_to_color(iterations: u32, max_iterations: u32) -> Rgb<u8> {
-    let t = iterations as f64 / max_iterations as f64;
-    let color = ((1.0 - (t)) * 255.0) as u8;
+/// Map an estimated distance-to-boundary to a color, so points near the boundary render dark and
+/// distant exterior points render light. `zoom` rescales the distance so the falloff looks
+/// consistent regardless of how far the view is zoomed in.
+fn distance_to_color(distance: f64, zoom: f64) -> Rgb<u8> {
+    let color = ((distance * zoom).tanh() * 255.0) as u8;
     Rgb([color, color, color])
 }
-
-/// Sample the mandelbrot set at the given location.
-/// Returns num iterations before the sequence diverged, or None if the sequence did not diverge.
-fn sample_mandelbrot(c: Complex<f64>, threshold: f64, iterations: u32) -> Option<u32> {
-    let threshold_squared = threshold * threshold;
-    let mut z = Complex::new(0.0, 0.0);
-    for iteration in 0..iterations {
-        z = (z * z) + c;
-        if z.norm_sqr() > threshold_squared {
-            return Some(iteration + 1)
-        }
-    }
-    return None;
-}
@@ -0,0 +1,116 @@
+use crate::Args;
+use crate::mandelbrot::sample::{complex_to_pixel, offset_and_center};
+use image::{Rgb, RgbImage};
+use indicatif::ProgressBar;
+use num::Complex;
+use rand::Rng;
+use rayon::prelude::*;
+
+/// Render the Buddhabrot: an orbit-density histogram of escaping Mandelbrot trajectories.
+///
+/// Unlike the escape-time render, brightness here comes from how often *escaping* orbits pass
+/// through a pixel rather than from how quickly the pixel's own point escapes. When `args.nebula`
+/// is set, three independent passes at different iteration caps are assigned to the red, green
+/// and blue channels (the "Nebulabrot" variant).
+pub fn render_buddhabrot(args: &Args, progress_bar: &ProgressBar) -> RgbImage {
+    if args.nebula {
+        let [r_cap, g_cap, b_cap] = nebula_caps(args.max_iterations);
+        let r = accumulate_histogram(args, r_cap, progress_bar);
+        let g = accumulate_histogram(args, g_cap, progress_bar);
+        let b = accumulate_histogram(args, b_cap, progress_bar);
+        histograms_to_image(args, &r, &g, &b)
+    } else {
+        let histogram = accumulate_histogram(args, args.max_iterations, progress_bar);
+        histograms_to_image(args, &histogram, &histogram, &histogram)
+    }
+}
+
+/// Escape-iteration caps used for the red, green, and blue passes of a Nebulabrot render.
+fn nebula_caps(max_iterations: u32) -> [u32; 3] {
+    [(max_iterations / 5).max(1), (max_iterations / 2).max(1), max_iterations]
+}
+
+/// Accumulate a histogram of how often escaping orbits visit each pixel.
+fn accumulate_histogram(args: &Args, max_iterations: u32, progress_bar: &ProgressBar) -> Vec<u32> {
+    let (offset, center) = offset_and_center(args);
+    let pixel_count = (args.x_res * args.y_res) as usize;
+
+    (0..args.buddhabrot_samples)
+        .into_par_iter()
+        .fold(
+            || vec![0u32; pixel_count],
+            |mut histogram, _| {
+                let c = random_point(args, center, offset);
+                if let Some(orbit) = escaped_orbit(c, args.threshold, max_iterations) {
+                    for z in orbit {
+                        if let Some(pixel) = complex_to_pixel(z, center, offset, args.zoom, args.x_res, args.y_res) {
+                            histogram[(pixel.y * args.x_res + pixel.x) as usize] += 1;
+                        }
+                    }
+                }
+                progress_bar.inc(1);
+                histogram
+            },
+        )
+        .reduce(
+            || vec![0u32; pixel_count],
+            |mut total, partial| {
+                for (sum, count) in total.iter_mut().zip(partial) {
+                    *sum += count;
+                }
+                total
+            },
+        )
+}
+
+/// Pick a point uniformly at random over the viewed region of the complex plane.
+fn random_point(args: &Args, center: Complex<f64>, offset: Complex<f64>) -> Complex<f64> {
+    let mut rng = rand::rng();
+    let re = rng.random_range(0.0..args.x_res as f64) / args.zoom;
+    let im = rng.random_range(0.0..args.y_res as f64) / args.zoom;
+    Complex::new(re, im) + offset - center
+}
+
+/// Iterate `z = z*z + c` recording the full orbit, returning it only if the orbit escapes.
+fn escaped_orbit(c: Complex<f64>, threshold: f64, max_iterations: u32) -> Option<Vec<Complex<f64>>> {
+    let threshold_squared = threshold * threshold;
+    let mut z = Complex::new(0.0, 0.0);
+    let mut orbit = Vec::with_capacity(max_iterations as usize);
+    for _ in 0..max_iterations {
+        z = (z * z) + c;
+        orbit.push(z);
+        if z.norm_sqr() > threshold_squared {
+            return Some(orbit);
+        }
+    }
+    None
+}
+
+/// Normalize three orbit-density histograms into an RGB image using a logarithmic falloff.
+fn histograms_to_image(args: &Args, r: &[u32], g: &[u32], b: &[u32]) -> RgbImage {
+    let mut image = RgbImage::new(args.x_res, args.y_res);
+    let r_max = r.iter().copied().max().unwrap_or(0);
+    let g_max = g.iter().copied().max().unwrap_or(0);
+    let b_max = b.iter().copied().max().unwrap_or(0);
+
+    for x in 0..args.x_res {
+        for y in 0..args.y_res {
+            let index = (y * args.x_res + x) as usize;
+            let pixel = Rgb([
+                normalize(r[index], r_max),
+                normalize(g[index], g_max),
+                normalize(b[index], b_max),
+            ]);
+            image.put_pixel(x, y, pixel);
+        }
+    }
+    image
+}
+
+/// Compress a histogram count into a displayable brightness using a logarithmic falloff.
+fn normalize(count: u32, max: u32) -> u8 {
+    if max == 0 {
+        return 0;
+    }
+    (((count as f64 + 1.0).ln() / (max as f64 + 1.0).ln()) * 255.0) as u8
+}
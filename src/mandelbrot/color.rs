@@ -0,0 +1,189 @@
+use clap::ValueEnum;
+use image::Rgb;
+use std::error::Error;
+use std::fs;
+
+/// Built-in color palettes for mapping a normalized escape value to an RGB color.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Palette {
+    /// Linear black-to-white ramp.
+    Grayscale,
+    /// Hue sweeps across the color wheel at full saturation and mid lightness.
+    Hsl,
+    /// Warm black -> red -> orange -> yellow -> white ramp.
+    Fire,
+    /// A smooth blue/gold/white ramp reminiscent of Ultra Fractal's default gradient.
+    UltraFractal,
+}
+
+impl Palette {
+    /// Map a normalized escape value `t` (0.0 = escaped immediately, 1.0 = escaped only at the
+    /// iteration limit) to a color.
+    pub fn color(self, t: f64) -> Rgb<u8> {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Palette::Grayscale => {
+                let shade = ((1.0 - t) * 255.0) as u8;
+                Rgb([shade, shade, shade])
+            },
+            Palette::Hsl => hsl_to_rgb(t * 360.0, 1.0, 0.5),
+            Palette::Fire => gradient(t, FIRE_STOPS),
+            Palette::UltraFractal => gradient(t, ULTRA_FRACTAL_STOPS),
+        }
+    }
+}
+
+const FIRE_STOPS: &[Rgb<u8>] = &[
+    Rgb([0, 0, 0]),
+    Rgb([128, 0, 0]),
+    Rgb([255, 128, 0]),
+    Rgb([255, 255, 0]),
+    Rgb([255, 255, 255]),
+];
+
+const ULTRA_FRACTAL_STOPS: &[Rgb<u8>] = &[
+    Rgb([0, 7, 100]),
+    Rgb([32, 107, 203]),
+    Rgb([237, 255, 255]),
+    Rgb([255, 170, 0]),
+    Rgb([0, 2, 0]),
+];
+
+/// Linearly interpolate `t` (0.0..=1.0) across a list of evenly spaced control-point colors, in
+/// linear RGB space.
+pub fn gradient(t: f64, stops: &[Rgb<u8>]) -> Rgb<u8> {
+    if stops.len() == 1 {
+        return stops[0];
+    }
+    let scaled = t * (stops.len() - 1) as f64;
+    let index = (scaled.floor() as usize).min(stops.len() - 2);
+    let fraction = scaled - index as f64;
+    lerp_rgb(stops[index], stops[index + 1], fraction)
+}
+
+/// Interpolate two sRGB colors by decoding to linear light, blending, and re-encoding, so the
+/// midpoint of e.g. black and white comes out perceptually gray rather than too dark.
+fn lerp_rgb(a: Rgb<u8>, b: Rgb<u8>, t: f64) -> Rgb<u8> {
+    let mut channels = [0u8; 3];
+    for i in 0..3 {
+        let blended = srgb_to_linear(a.0[i]) + (srgb_to_linear(b.0[i]) - srgb_to_linear(a.0[i])) * t;
+        channels[i] = linear_to_srgb(blended);
+    }
+    Rgb(channels)
+}
+
+/// Decode an 8-bit sRGB channel value to linear light (`0.0..=1.0`).
+fn srgb_to_linear(channel: u8) -> f64 {
+    let c = channel as f64 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Encode a linear-light value (`0.0..=1.0`) back to an 8-bit sRGB channel value.
+fn linear_to_srgb(linear: f64) -> u8 {
+    let c = linear.clamp(0.0, 1.0);
+    let encoded = if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+    (encoded * 255.0).round() as u8
+}
+
+/// Convert HSL (hue in degrees, saturation/lightness in `0.0..=1.0`) to 8-bit RGB.
+fn hsl_to_rgb(hue: f64, saturation: f64, lightness: f64) -> Rgb<u8> {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let h_prime = hue / 60.0;
+    let x = chroma * (1.0 - (h_prime % 2.0 - 1.0).abs());
+    let (r1, g1, b1) = match h_prime as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let lightness_match = lightness - chroma / 2.0;
+    Rgb([
+        ((r1 + lightness_match) * 255.0).round() as u8,
+        ((g1 + lightness_match) * 255.0).round() as u8,
+        ((b1 + lightness_match) * 255.0).round() as u8,
+    ])
+}
+
+/// Load a custom gradient from a file containing one `#RRGGBB` hex color per line.
+pub fn load_gradient(path: &str) -> Result<Vec<Rgb<u8>>, Box<dyn Error>> {
+    let contents = fs::read_to_string(path)?;
+    let stops: Vec<Rgb<u8>> = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(parse_hex_color)
+        .collect::<Result<_, _>>()?;
+    if stops.is_empty() {
+        return Err(format!("gradient file {path:?} contains no colors").into());
+    }
+    Ok(stops)
+}
+
+fn parse_hex_color(line: &str) -> Result<Rgb<u8>, Box<dyn Error>> {
+    let hex = line.strip_prefix('#').unwrap_or(line);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(format!("invalid gradient color {line:?}: expected #RRGGBB").into());
+    }
+    let r = u8::from_str_radix(&hex[0..2], 16)?;
+    let g = u8::from_str_radix(&hex[2..4], 16)?;
+    let b = u8::from_str_radix(&hex[4..6], 16)?;
+    Ok(Rgb([r, g, b]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hsl_to_rgb_primary_hues() {
+        assert_eq!(hsl_to_rgb(0.0, 1.0, 0.5), Rgb([255, 0, 0]));
+        assert_eq!(hsl_to_rgb(120.0, 1.0, 0.5), Rgb([0, 255, 0]));
+        assert_eq!(hsl_to_rgb(240.0, 1.0, 0.5), Rgb([0, 0, 255]));
+    }
+
+    #[test]
+    fn gradient_returns_exact_stops_at_its_endpoints() {
+        assert_eq!(gradient(0.0, FIRE_STOPS), FIRE_STOPS[0]);
+        assert_eq!(gradient(1.0, FIRE_STOPS), FIRE_STOPS[FIRE_STOPS.len() - 1]);
+    }
+
+    #[test]
+    fn gradient_interpolates_in_linear_light_not_raw_srgb() {
+        let black_to_white = [Rgb([0, 0, 0]), Rgb([255, 255, 255])];
+        let midpoint = gradient(0.5, &black_to_white);
+        // A raw sRGB-space lerp would land near 128; linear-light blending comes out brighter.
+        assert!(midpoint.0[0] > 150, "expected a linear-light midpoint, got {midpoint:?}");
+    }
+
+    #[test]
+    fn parse_hex_color_accepts_with_or_without_hash() {
+        assert_eq!(parse_hex_color("#ff0080").unwrap(), Rgb([0xff, 0x00, 0x80]));
+        assert_eq!(parse_hex_color("ff0080").unwrap(), Rgb([0xff, 0x00, 0x80]));
+    }
+
+    #[test]
+    fn parse_hex_color_rejects_malformed_input() {
+        assert!(parse_hex_color("#abc").is_err());
+        assert!(parse_hex_color("#abcdez").is_err());
+        assert!(parse_hex_color("#abcdé0").is_err());
+    }
+
+    #[test]
+    fn load_gradient_rejects_a_blank_file() {
+        let path = std::env::temp_dir().join(format!("mandelbrot-test-gradient-{}.txt", std::process::id()));
+        fs::write(&path, "\n\n   \n").unwrap();
+        let result = load_gradient(path.to_str().unwrap());
+        fs::remove_file(&path).ok();
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,15 @@
+pub mod buddhabrot;
+pub mod color;
+pub mod sample;
+pub mod simd;
+
+/// Which fractal rendering algorithm to use.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// The classic escape-time Mandelbrot set render.
+    Mandelbrot,
+    /// Orbit-density render of escaping points, optionally split into RGB passes (Nebulabrot).
+    Buddhabrot,
+    /// Escape-time render of the Julia set for a fixed constant (`--julia-real`/`--julia-imag`).
+    Julia,
+}
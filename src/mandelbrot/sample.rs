@@ -1,4 +1,5 @@
 use crate::Args;
+use crate::mandelbrot::Mode;
 use indicatif::ProgressBar;
 use num::Complex;
 use num::integer::Roots;
@@ -6,55 +7,196 @@ use rand::Rng;
 use rayon::prelude::*;
 
 #[derive(Copy, Clone)]
-struct Pixel {
-    x: u32,
-    y: u32,
+pub(crate) struct Pixel {
+    pub(crate) x: u32,
+    pub(crate) y: u32,
 }
 
 pub struct SampleResult {
     pub x_res: u32,
     pub y_res: u32,
     pub grid: Vec<Vec<Option<f64>>>,
+    /// Estimated distance from each pixel to the set boundary, for distance-estimate coloring.
+    /// `None` wherever the point did not escape (i.e. is inside the set).
+    pub distance: Vec<Vec<Option<f64>>>,
 }
 
-pub fn sample_grid(args: &Args, progress_bar: &ProgressBar) -> SampleResult {
+/// A single escape-time sample of an orbit that escaped: the (possibly smoothed) escape value and
+/// the estimated distance to the set boundary. A point whose orbit does not escape has no `Sample`.
+#[derive(Copy, Clone)]
+struct Sample {
+    escape: f64,
+    distance: f64,
+}
+
+/// Compute the `offset`/`center` terms shared by every pixel-to-complex conversion for a view.
+pub(crate) fn offset_and_center(args: &Args) -> (Complex<f64>, Complex<f64>) {
     let offset = Complex::new(args.real_offset, args.complex_offset);
     let center = (Complex::new(args.x_res as f64, args.y_res as f64) / args.zoom) / 2f64;
-    let mut result = vec![vec![None; args.y_res as usize]; args.x_res as usize];
-
-    // TODO: Degree of parallelism shouldn't depend on the image size & aspect ratio
-    result.par_iter_mut().enumerate().for_each(|(x, column)| {
-        for y in 0..args.y_res {
-            let sample = sample_pixel(args, offset, center, x as u32, y);
-            column[y as usize] = sample;
-            if y % 100 == 0 {
-                progress_bar.inc(100);
-            }
-        }
-    });
+    (offset, center)
+}
+
+/// Sample the full grid by running every stage of [`sample_grid_progressive`] with no per-stage
+/// callback. The result is that of its last, full-resolution stage.
+pub fn sample_grid(args: &Args, progress_bar: &ProgressBar) -> SampleResult {
+    sample_grid_progressive(args, progress_bar, |_| {})
+}
 
-    SampleResult {
+/// Sample the grid in coarse-to-fine stages, calling `on_stage` with a full-resolution (but blocky,
+/// on all but the last stage) result after each one. This lets a caller display a preview almost
+/// immediately instead of waiting for the full-resolution render to finish.
+pub fn sample_grid_progressive<F>(args: &Args, progress_bar: &ProgressBar, mut on_stage: F) -> SampleResult
+where
+    F: FnMut(&SampleResult),
+{
+    let (offset, center) = offset_and_center(args);
+    let mut result = SampleResult {
         x_res: args.x_res,
         y_res: args.y_res,
-        grid: result,
+        grid: vec![vec![None; args.y_res as usize]; args.x_res as usize],
+        distance: vec![vec![None; args.y_res as usize]; args.x_res as usize],
+    };
+
+    for stride in progressive_strides(args.x_res, args.y_res) {
+        // TODO: Degree of parallelism shouldn't depend on the image size & aspect ratio
+        result.grid.par_iter_mut().zip(result.distance.par_iter_mut()).enumerate()
+            .filter(|(x, _)| *x as u32 % stride == 0)
+            .for_each(|(x, (escape_column, distance_column))| {
+                let mut y = 0;
+                while y < args.y_res {
+                    let sample = sample_pixel(args, offset, center, x as u32, y);
+                    escape_column[y as usize] = sample.map(|s| s.escape);
+                    distance_column[y as usize] = sample.map(|s| s.distance);
+                    y += stride;
+                }
+                progress_bar.inc((args.y_res / stride).max(1) as u64);
+            });
+
+        block_fill(&mut result.grid, args.x_res, args.y_res, stride);
+        block_fill(&mut result.distance, args.x_res, args.y_res, stride);
+        on_stage(&result);
     }
+
+    result
+}
+
+/// Stride lengths to sample at, coarsest first, always ending at a stride of 1 (full resolution).
+fn progressive_strides(x_res: u32, y_res: u32) -> Vec<u32> {
+    let shortest_side = x_res.min(y_res);
+    [8, 4, 2, 1].into_iter().filter(|&stride| stride == 1 || stride < shortest_side).collect()
 }
 
-fn sample_pixel(args: &Args, offset: Complex<f64>, center: Complex<f64>, x: u32, y: u32) -> Option<f64> {
+/// Total progress [`sample_grid_progressive`] reports across all of its stages, for sizing a progress
+/// bar. Mirrors its per-stage column count and per-column progress increment (`y_res / stride`, at
+/// least 1) exactly, since every stage after the first re-samples pixels already covered by coarser strides.
+pub fn progressive_work(x_res: u32, y_res: u32) -> u64 {
+    progressive_strides(x_res, y_res)
+        .into_iter()
+        .map(|stride| {
+            let columns = (0..x_res).step_by(stride as usize).count() as u64;
+            let rows_per_column = (y_res / stride).max(1) as u64;
+            columns * rows_per_column
+        })
+        .sum()
+}
+
+/// Fill every cell with the value sampled at the top-left corner of its `stride`-sized block, so a
+/// sparsely-sampled stage still renders as a full (if blocky) preview image.
+fn block_fill(grid: &mut [Vec<Option<f64>>], x_res: u32, y_res: u32, stride: u32) {
+    if stride <= 1 {
+        return;
+    }
+    for x in 0..x_res as usize {
+        let sample_x = x - x % stride as usize;
+        for y in 0..y_res as usize {
+            let sample_y = y - y % stride as usize;
+            if sample_x != x || sample_y != y {
+                grid[x][y] = grid[sample_x][sample_y];
+            }
+        }
+    }
+}
+
+/// If the per-pixel step size has shrunk below what `f64` can resolve against the current view
+/// offset, zooming further can't reveal any more real detail. Escape-time detail still scales with
+/// iteration count at this depth, so bump `max_iterations` instead and warn that precision is spent.
+///
+/// `last_exhaustion_zoom` tracks the zoom level at which `max_iterations` was last bumped (`None`
+/// if it never has been). Doubling the zoom costs roughly one more bit of precision, so this only
+/// bumps again once the zoom has roughly doubled past that point — not on every notch past the
+/// threshold — and never bumps on a zoom-out, since losing precision headroom isn't a reason to
+/// grow `max_iterations` further. Without this, an interactive session parked near the precision
+/// wall would double `max_iterations` on every single scroll notch, in either direction.
+pub fn check_precision(args: &mut Args, last_exhaustion_zoom: &mut Option<f64>) {
+    let step = 1.0 / args.zoom;
+    let offset_magnitude = args.real_offset.abs().max(args.complex_offset.abs()).max(1.0);
+    if step >= offset_magnitude * f64::EPSILON {
+        return;
+    }
+
+    let already_bumped_at = last_exhaustion_zoom.unwrap_or(0.0);
+    if args.zoom < already_bumped_at * 2.0 {
+        return;
+    }
+
+    eprintln!(
+        "warning: f64 precision exhausted at zoom {:.3e}; increasing max_iterations instead of sharpening detail",
+        args.zoom
+    );
+    args.max_iterations = args.max_iterations.saturating_mul(2);
+    *last_exhaustion_zoom = Some(args.zoom);
+}
+
+fn sample_pixel(args: &Args, offset: Complex<f64>, center: Complex<f64>, x: u32, y: u32) -> Option<Sample> {
     let location: Complex<f64> = pixel_to_complex(Pixel { x, y }, center, offset, args.zoom);
     super_sample_mandelbrot(args, location)
 }
 
+/// Fill `column[y_start..y_res]` using the scalar sampler. Used by the SIMD grid to finish off a
+/// column whose remaining height doesn't fill a full lane group.
+pub(crate) fn sample_column_scalar(
+    args: &Args,
+    offset: Complex<f64>,
+    center: Complex<f64>,
+    x: u32,
+    y_start: u32,
+    y_res: u32,
+    column: &mut [Option<f64>],
+) {
+    for y in y_start..y_res {
+        column[y as usize] = sample_pixel(args, offset, center, x, y).map(|sample| sample.escape);
+    }
+}
+
 /// Convert a pixel location to a location on the complex plane.
-fn pixel_to_complex(location: Pixel, center: Complex<f64>, offset: Complex<f64>, zoom: f64) -> Complex<f64> {
+pub(crate) fn pixel_to_complex(location: Pixel, center: Complex<f64>, offset: Complex<f64>, zoom: f64) -> Complex<f64> {
     let sample = Complex::new(location.x as f64, location.y as f64) / zoom;
     sample + offset - center
 }
 
+/// Convert a location on the complex plane back to the nearest pixel location, if it falls within the image bounds.
+pub(crate) fn complex_to_pixel(
+    location: Complex<f64>,
+    center: Complex<f64>,
+    offset: Complex<f64>,
+    zoom: f64,
+    x_res: u32,
+    y_res: u32,
+) -> Option<Pixel> {
+    let pixel = (location - offset + center) * zoom;
+    let (x, y) = (pixel.re.round(), pixel.im.round());
+    if x >= 0.0 && x < x_res as f64 && y >= 0.0 && y < y_res as f64 {
+        Some(Pixel { x: x as u32, y: y as u32 })
+    } else {
+        None
+    }
+}
+
 /// Returns the average of multiple samples within a given range. Sampling uses a "jitter" strategy.
 /// https://en.wikipedia.org/wiki/Supersampling.
-fn super_sample_mandelbrot(args: &Args, c: Complex<f64>) -> Option<f64> {
-    let mut sum = 0f64;
+fn super_sample_mandelbrot(args: &Args, c: Complex<f64>) -> Option<Sample> {
+    let mut escape_sum = 0f64;
+    let mut distance_sum = 0f64;
     let mut diverged_samples = 0;
     let subpixel_width = (1.0 / args.zoom) / (args.samples as f64).sqrt();
 
@@ -64,14 +206,18 @@ fn super_sample_mandelbrot(args: &Args, c: Complex<f64>) -> Option<f64> {
                 dx as f64 * subpixel_width, dy as f64 * subpixel_width);
             let sample_location = random_offset(subpixel_center, subpixel_width);
             if let Some(sample) = sample_mandelbrot(args, sample_location) {
-                sum += sample;
+                escape_sum += sample.escape;
+                distance_sum += sample.distance;
                 diverged_samples += 1
             }
         }
     }
 
     if diverged_samples > 0 {
-        Some(sum / diverged_samples as f64)
+        Some(Sample {
+            escape: escape_sum / diverged_samples as f64,
+            distance: distance_sum / diverged_samples as f64,
+        })
     } else {
         None
     }
@@ -85,18 +231,42 @@ fn random_offset(c: Complex<f64>, range: f64) -> Complex<f64> {
     c + Complex::new(re, im)
 }
 
-/// Sample the mandelbrot set at the given location.
-/// Returns num iterations before the sequence diverged, or None if the sequence did not diverge.
-fn sample_mandelbrot(args: &Args, c: Complex<f64>) -> Option<f64> {
-    let mut z = Complex::new(0.0, 0.0);
+/// Sample the mandelbrot (or, in `Mode::Julia`, the Julia) set at the given location.
+/// Returns the escape sample if the sequence diverged, or None if it did not diverge.
+///
+/// In Mandelbrot mode `z` starts at the origin and `pixel_location` is added each step; in Julia
+/// mode `z` starts at `pixel_location` and the fixed `--julia-real`/`--julia-imag` constant is
+/// added each step instead.
+///
+/// Alongside `z`, this carries the derivative `dz` of `z` with respect to the starting point so
+/// that, on escape, a distance-to-boundary estimate can be derived via `|z| * ln(|z|) / |dz|`.
+/// See https://en.wikipedia.org/wiki/Mandelbrot_set#Distance_estimation.
+fn sample_mandelbrot(args: &Args, pixel_location: Complex<f64>) -> Option<Sample> {
+    let (mut z, k) = match args.mode {
+        Mode::Julia => (pixel_location, Complex::new(args.julia_real, args.julia_imag)),
+        _ => (Complex::new(0.0, 0.0), pixel_location),
+    };
+    // `dz` is the derivative of `z` with respect to the value that varies per pixel: in Mandelbrot
+    // mode that's `c` (so `dz` starts at 0, with a `+1` term from `d/dc[z^2 + c]`); in Julia mode
+    // it's the starting `z`, which contributes no such constant term, so `dz` starts at 1 instead.
+    let mut dz = match args.mode {
+        Mode::Julia => Complex::new(1.0, 0.0),
+        _ => Complex::new(0.0, 0.0),
+    };
     for iteration in 0..args.max_iterations {
-        z = (z * z) + c;
+        dz = match args.mode {
+            Mode::Julia => 2.0 * z * dz,
+            _ => 2.0 * z * dz + Complex::new(1.0, 0.0),
+        };
+        z = (z * z) + k;
         if f64::hypot(z.re, z.im) > args.threshold {
-            return if args.smooth {
-                Some(smooth_iteration(iteration, z))
+            let escape = if args.smooth {
+                smooth_iteration(iteration, z)
             } else {
-                Some((iteration + 1) as f64)
-            }
+                (iteration + 1) as f64
+            };
+            let distance = z.norm() * z.norm().ln() / dz.norm();
+            return Some(Sample { escape, distance });
         }
     }
     None
@@ -110,3 +280,29 @@ fn sample_mandelbrot(args: &Args, c: Complex<f64>) -> Option<f64> {
 fn smooth_iteration(iteration: u32, z: Complex<f64>) -> f64 {
     iteration as f64 + 1.0 - ((z.norm().ln() / 2.0_f64.ln()).ln() / 2.0_f64.ln())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pixel_to_complex_round_trips_through_complex_to_pixel() {
+        let offset = Complex::new(-1.0, 0.3);
+        let center = Complex::new(2.0, 2.0);
+        let zoom = 250.0;
+        let pixel = Pixel { x: 37, y: 112 };
+
+        let location = pixel_to_complex(pixel, center, offset, zoom);
+        let recovered = complex_to_pixel(location, center, offset, zoom, 1000, 1000).unwrap();
+
+        assert_eq!(recovered.x, pixel.x);
+        assert_eq!(recovered.y, pixel.y);
+    }
+
+    #[test]
+    fn complex_to_pixel_rejects_points_outside_the_image() {
+        let offset = Complex::new(0.0, 0.0);
+        let center = Complex::new(0.0, 0.0);
+        assert!(complex_to_pixel(Complex::new(-10.0, -10.0), center, offset, 1.0, 100, 100).is_none());
+    }
+}